@@ -3,12 +3,18 @@
 use crate::Result;
 
 use std::collections::HashMap;
+use std::hash::Hasher;
 use std::ptr;
 use std::time::Duration;
 use std::vec::Vec;
 
 use uplink_sys as ulksys;
 
+#[cfg(feature = "encryption")]
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+#[cfg(feature = "encryption")]
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+
 /// It's a container for custom information of a specific "item".
 /// It's provided by the users as key-value pairs which must only contain valid
 /// UTF-8 characters. Keys are unique, so only one value can be associated with
@@ -148,6 +154,156 @@ impl Custom {
 
         self.inner.as_ref().unwrap().custom_metadata
     }
+
+    /// The key prefix reserved for entries created through
+    /// [`Self::insert_bytes`], whose value is base64-encoded binary data
+    /// rather than a plain UTF-8 string.
+    const BYTES_KEY_PREFIX: &'static str = "b64:";
+
+    /// Inserts a new entry with the specified key and an arbitrary binary
+    /// value, returning false if the key didn't exist, otherwise true and
+    /// replacing the value associated to the key.
+    ///
+    /// Because entries are stored as UTF-8 strings, `value` is transparently
+    /// base64-encoded before being stored, and the key is recorded with the
+    /// reserved `"b64:"` prefix so [`Self::get_bytes`] knows how to reverse
+    /// it. Use [`Self::get_bytes`], not [`Self::get`], to read the value back.
+    pub fn insert_bytes(&mut self, key: &str, value: &[u8]) -> bool {
+        self.insert(
+            &format!("{}{}", Self::BYTES_KEY_PREFIX, key),
+            &base64_encode(value),
+        )
+    }
+
+    /// Gets the binary value associated with the passed key that was
+    /// previously inserted with [`Self::insert_bytes`]. Returns `None` if
+    /// there isn't any entry associated to the key, or `Some(Err(_))` if the
+    /// stored value isn't valid base64-encoded data.
+    pub fn get_bytes(&self, key: &str) -> Option<Result<Vec<u8>>> {
+        let encoded = self.get(&format!("{}{}", Self::BYTES_KEY_PREFIX, key))?;
+        Some(base64_decode(encoded))
+    }
+
+    /// Returns a deterministic, order-independent fingerprint of all the
+    /// key-value pairs this instance holds.
+    ///
+    /// Two instances containing the same entries always produce the same
+    /// fingerprint regardless of the order the entries were inserted in,
+    /// so callers can cheaply detect whether a metadata set changed between
+    /// reads or writes without comparing every entry.
+    pub fn fingerprint(&self) -> u128 {
+        let mut acc_lo: u64 = 0;
+        let mut acc_hi: u64 = 0;
+
+        for (k, v) in self.entries.iter() {
+            acc_lo ^= Self::entry_hash(k, v, 0);
+            acc_hi ^= Self::entry_hash(k, v, 1);
+        }
+
+        (u128::from(acc_hi) << 64) | u128::from(acc_lo)
+    }
+
+    /// Returns a new instance containing only the entries for which `pred`
+    /// returns `true`.
+    ///
+    /// `pred` can be one of the built-in predicates in the [`query`] module,
+    /// or a combination of them built with [`query::and`], [`query::or`],
+    /// and [`query::not`]. This lets applications project a subset of
+    /// metadata, e.g. all `"image-board:"` entries, into a new [`Custom`]
+    /// for re-upload without hand-rolling iteration over [`Self::iter`].
+    pub fn filter<F: Fn(&str, &str) -> bool>(&self, pred: F) -> Self {
+        let entries = self
+            .entries
+            .iter()
+            .filter(|(k, v)| pred(k, v))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+
+        Self {
+            entries,
+            inner: None,
+        }
+    }
+
+    /// Hashes a single key-value pair with a fixed-seed hasher, prefixing
+    /// each part with its length to avoid collisions between e.g.
+    /// `("ab", "c")` and `("a", "bc")`.
+    ///
+    /// `salt` lets [`Self::fingerprint`] derive two decorrelated 64-bit
+    /// hashes per entry, which are combined into the returned 128-bit value.
+    fn entry_hash(key: &str, value: &str, salt: u64) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        hasher.write_u64(salt);
+        hasher.write_usize(key.len());
+        hasher.write(key.as_bytes());
+        hasher.write_usize(value.len());
+        hasher.write(value.as_bytes());
+        hasher.finish()
+    }
+
+    /// Gets the passed key's corresponding entry for in-place insertion or
+    /// modification, invalidating the cached c-bindings representation only
+    /// if the entry ends up being changed.
+    pub fn entry(&mut self, key: &str) -> Entry<'_> {
+        Entry {
+            key: key.into(),
+            custom: self,
+        }
+    }
+
+    /// Inserts all the passed key-value pairs, replacing the value of any
+    /// key that already existed.
+    ///
+    /// Unlike calling [`Self::insert`] in a loop, this invalidates the
+    /// cached c-bindings representation exactly once regardless of how many
+    /// entries are inserted.
+    pub fn extend_entries(&mut self, entries: &[(&str, &str)]) {
+        for (k, v) in entries {
+            self.entries.insert((*k).into(), (*v).into());
+        }
+        self.inner = None;
+    }
+
+    /// Retains only the entries for which `f` returns `true`, removing the
+    /// rest.
+    ///
+    /// Unlike calling [`Self::delete`] in a loop, this invalidates the
+    /// cached c-bindings representation exactly once regardless of how many
+    /// entries are removed.
+    pub fn retain<F: FnMut(&str, &str) -> bool>(&mut self, mut f: F) {
+        self.entries.retain(|k, v| f(k, v));
+        self.inner = None;
+    }
+}
+
+/// A view into a single entry of a [`Custom`] metadata container, obtained
+/// from [`Custom::entry`].
+pub struct Entry<'a> {
+    custom: &'a mut Custom,
+    key: Box<str>,
+}
+
+impl<'a> Entry<'a> {
+    /// Ensures the entry has a value, inserting `default` if it's currently
+    /// vacant, then returns the value associated with the key.
+    pub fn or_insert(self, default: &str) -> &'a str {
+        if !self.custom.entries.contains_key(&self.key) {
+            self.custom.entries.insert(self.key.clone(), default.into());
+            self.custom.inner = None;
+        }
+        &self.custom.entries[&self.key]
+    }
+
+    /// Calls `f` with the current value if the entry is occupied, replacing
+    /// it with whatever `f` returns. Does nothing if the entry is vacant.
+    pub fn and_modify<F: FnOnce(&str) -> String>(self, f: F) -> Self {
+        if let Some(v) = self.custom.entries.get(&self.key) {
+            let new_value = f(v).into_boxed_str();
+            self.custom.entries.insert(self.key.clone(), new_value);
+            self.custom.inner = None;
+        }
+        self
+    }
 }
 
 impl Clone for Custom {
@@ -159,6 +315,251 @@ impl Clone for Custom {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for Custom {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeMap;
+
+        let mut map = serializer.serialize_map(Some(self.entries.len()))?;
+        for (k, v) in self.entries.iter() {
+            map.serialize_entry(k.as_ref(), v.as_ref())?;
+        }
+        map.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Custom {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        // The `inner` cache isn't (de)serialized because the deserialized
+        // entries haven't built a c-bindings representation yet; it's
+        // rebuilt lazily the next time `to_uplink_c` is called.
+        let entries = HashMap::<Box<str>, Box<str>>::deserialize(deserializer)?;
+        Ok(Self {
+            entries,
+            inner: None,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Custom {
+    /// Serializes this custom metadata container into a JSON object whose
+    /// keys and values are the metadata's keys and values.
+    ///
+    /// This allows an application to snapshot, diff, or transmit a metadata
+    /// set without going through the uplink c-bindings.
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string(self).map_err(crate::Error::from)
+    }
+
+    /// Creates a custom metadata instance from a JSON object of string keys
+    /// to string values previously produced by [`Self::to_json`].
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(crate::Error::from)
+    }
+}
+
+#[cfg(feature = "encryption")]
+impl Custom {
+    /// The key prefix reserved for entries whose value has been replaced
+    /// with its encrypted form by [`Self::encrypt_value`].
+    const ENCRYPTED_KEY_PREFIX: &'static str = "enc:";
+
+    /// Encrypts the value associated with `key` in place with
+    /// ChaCha20-Poly1305, using `key_material` as the 256-bit AEAD key.
+    ///
+    /// A fresh random 12-byte nonce is generated for this call, and the
+    /// plain entry is replaced with `nonce || ciphertext || tag`,
+    /// base64-encoded, stored under the reserved `"enc:"` key prefix. Call
+    /// [`Self::decrypt_value`] with the same `key_material` to reverse it.
+    ///
+    /// Returns an error if there's no entry associated with `key`.
+    ///
+    /// `key` itself is bound as AEAD associated data, so a ciphertext
+    /// produced for one key can't be pasted under another key and still
+    /// authenticate on [`Self::decrypt_value`].
+    ///
+    /// NOTE additionally binding the object's bucket as associated data
+    /// would further prevent a value being swapped across objects; this
+    /// isn't done here because `Custom` has no notion of which object it's
+    /// attached to.
+    pub fn encrypt_value(&mut self, key: &str, key_material: &[u8; 32]) -> Result<()> {
+        // Only peek at the entry here: it must stay in place until
+        // encryption succeeds, so a failed call doesn't discard the only
+        // copy of the plaintext value.
+        let plaintext = self.entries.get(key).ok_or_else(|| {
+            crate::Error::new_invalid_argument(format!("no entry found for key '{key}'"))
+        })?;
+
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(key_material));
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plaintext.as_bytes(),
+                    aad: key.as_bytes(),
+                },
+            )
+            .map_err(|_| crate::Error::new_invalid_argument("failed to encrypt value"))?;
+
+        let mut payload = Vec::with_capacity(nonce.len() + ciphertext.len());
+        payload.extend_from_slice(&nonce);
+        payload.extend_from_slice(&ciphertext);
+
+        self.entries.remove(key);
+        self.entries.insert(
+            format!("{}{}", Self::ENCRYPTED_KEY_PREFIX, key).into_boxed_str(),
+            base64_encode(&payload).into_boxed_str(),
+        );
+        self.inner = None;
+
+        Ok(())
+    }
+
+    /// Decrypts the value previously encrypted by [`Self::encrypt_value`]
+    /// for `key`, replacing the encrypted entry with its plaintext.
+    ///
+    /// Returns an error, never the unauthenticated plaintext, if there's no
+    /// encrypted entry for `key`, if the stored value isn't validly
+    /// base64-encoded, or if `key_material` doesn't authenticate it. On any
+    /// error the encrypted entry is left untouched, so a failed decryption
+    /// never loses the encrypted value.
+    pub fn decrypt_value(&mut self, key: &str, key_material: &[u8; 32]) -> Result<()> {
+        let encrypted_key = format!("{}{}", Self::ENCRYPTED_KEY_PREFIX, key);
+        // Only peek at the entry here: it must stay in place until
+        // decryption and authentication both succeed, so a failed call
+        // doesn't discard the only copy of the encrypted value.
+        let payload = self.entries.get(encrypted_key.as_str()).ok_or_else(|| {
+            crate::Error::new_invalid_argument(format!("no encrypted entry found for key '{key}'"))
+        })?;
+        let payload = base64_decode(payload)?;
+
+        if payload.len() < 12 + 16 {
+            return Err(crate::Error::new_invalid_argument(
+                "encrypted value is too short to contain a nonce and an authentication tag",
+            ));
+        }
+        let (nonce, ciphertext) = payload.split_at(12);
+
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(key_material));
+        let plaintext = cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: key.as_bytes(),
+                },
+            )
+            .map_err(|_| {
+                crate::Error::new_invalid_argument("failed to authenticate encrypted value")
+            })?;
+        let plaintext = String::from_utf8(plaintext)
+            .map_err(|_| crate::Error::new_invalid_argument("decrypted value isn't valid UTF-8"))?;
+
+        self.entries.remove(encrypted_key.as_str());
+        self.entries.insert(key.into(), plaintext.into_boxed_str());
+        self.inner = None;
+
+        Ok(())
+    }
+}
+
+/// The standard base64 alphabet (RFC 4648) used to encode and decode the
+/// binary values stored through [`Custom::insert_bytes`].
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes `data` into a standard, padded base64 string.
+fn base64_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity((data.len() + 2) / 3 * 4);
+
+    for chunk in data.chunks(3) {
+        let b0 = chunk[0];
+        let b1 = chunk.get(1).copied().unwrap_or(0);
+        let b2 = chunk.get(2).copied().unwrap_or(0);
+
+        out.push(BASE64_ALPHABET[(b0 >> 2) as usize] as char);
+        out.push(BASE64_ALPHABET[(((b0 & 0b0000_0011) << 4) | (b1 >> 4)) as usize] as char);
+        out.push(if chunk.len() > 1 {
+            BASE64_ALPHABET[(((b1 & 0b0000_1111) << 2) | (b2 >> 6)) as usize] as char
+        } else {
+            '='
+        });
+        out.push(if chunk.len() > 2 {
+            BASE64_ALPHABET[(b2 & 0b0011_1111) as usize] as char
+        } else {
+            '='
+        });
+    }
+
+    out
+}
+
+/// Decodes a standard, padded base64 string back into its binary value.
+///
+/// Returns an error if `s`'s length isn't a multiple of 4 or if it contains
+/// a symbol outside of the standard base64 alphabet.
+fn base64_decode(s: &str) -> Result<Vec<u8>> {
+    if s.len() % 4 != 0 {
+        return Err(crate::Error::new_invalid_argument(format!(
+            "base64 encoded value's length ({}) isn't a multiple of 4",
+            s.len()
+        )));
+    }
+
+    fn decode_symbol(b: u8) -> Result<u8> {
+        match b {
+            b'A'..=b'Z' => Ok(b - b'A'),
+            b'a'..=b'z' => Ok(b - b'a' + 26),
+            b'0'..=b'9' => Ok(b - b'0' + 52),
+            b'+' => Ok(62),
+            b'/' => Ok(63),
+            _ => Err(crate::Error::new_invalid_argument(format!(
+                "invalid base64 symbol: '{}'",
+                b as char
+            ))),
+        }
+    }
+
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len() / 4 * 3);
+
+    for chunk in bytes.chunks(4) {
+        let padding = chunk.iter().filter(|&&b| b == b'=').count();
+
+        let c0 = decode_symbol(chunk[0])?;
+        let c1 = decode_symbol(chunk[1])?;
+        let c2 = if chunk[2] == b'=' {
+            0
+        } else {
+            decode_symbol(chunk[2])?
+        };
+        let c3 = if chunk[3] == b'=' {
+            0
+        } else {
+            decode_symbol(chunk[3])?
+        };
+
+        out.push((c0 << 2) | (c1 >> 4));
+        if padding < 2 {
+            out.push((c1 << 4) | (c2 >> 2));
+        }
+        if padding < 1 {
+            out.push((c2 << 6) | c3);
+        }
+    }
+
+    Ok(out)
+}
+
 /// It allows to create an [`uplink_sys::UplinkCustomMetadata`] instance that
 /// guards the used memory of its list of items during the lifetime of the
 /// instance of this struct.
@@ -211,6 +612,55 @@ impl Default for UplinkCustomMetadataWrapper {
     }
 }
 
+/// Predicates for selecting subsets of a [`Custom`] metadata container with
+/// [`Custom::filter`].
+///
+/// Predicates are plain `Fn(&str, &str) -> bool` closures over a key-value
+/// pair, so the built-in ones here compose with the [`and`], [`or`], and
+/// [`not`] combinators.
+pub mod query {
+    /// Matches entries whose key starts with `prefix`.
+    pub fn key_has_prefix(prefix: &str) -> impl Fn(&str, &str) -> bool + '_ {
+        move |key, _value| key.starts_with(prefix)
+    }
+
+    /// Matches entries whose key is exactly `key`.
+    pub fn key_matches(key: &str) -> impl Fn(&str, &str) -> bool + '_ {
+        move |k, _value| k == key
+    }
+
+    /// Matches entries whose value is exactly `value`.
+    pub fn value_eq(value: &str) -> impl Fn(&str, &str) -> bool + '_ {
+        move |_key, v| v == value
+    }
+
+    /// Matches entries whose value contains `needle`.
+    pub fn value_contains(needle: &str) -> impl Fn(&str, &str) -> bool + '_ {
+        move |_key, v| v.contains(needle)
+    }
+
+    /// Combines two predicates, matching entries that satisfy both.
+    pub fn and<'a>(
+        a: impl Fn(&str, &str) -> bool + 'a,
+        b: impl Fn(&str, &str) -> bool + 'a,
+    ) -> impl Fn(&str, &str) -> bool + 'a {
+        move |key, value| a(key, value) && b(key, value)
+    }
+
+    /// Combines two predicates, matching entries that satisfy either.
+    pub fn or<'a>(
+        a: impl Fn(&str, &str) -> bool + 'a,
+        b: impl Fn(&str, &str) -> bool + 'a,
+    ) -> impl Fn(&str, &str) -> bool + 'a {
+        move |key, value| a(key, value) || b(key, value)
+    }
+
+    /// Negates a predicate, matching entries that don't satisfy it.
+    pub fn not<'a>(a: impl Fn(&str, &str) -> bool + 'a) -> impl Fn(&str, &str) -> bool + 'a {
+        move |key, value| !a(key, value)
+    }
+}
+
 /// It's a container of system information of a specific "item".
 /// It's provided by the service and only the service can alter it.
 pub struct System {
@@ -383,6 +833,291 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_custom_insert_bytes_get_bytes() {
+        let key = "thumbnail";
+        let value = [0u8, 159, 146, 150, 255];
+
+        let mut custom = Custom::default();
+        assert!(!custom.insert_bytes(key, &value), "insert_bytes 'key'");
+        assert_eq!(custom.count(), 1, "count after inserting a binary value");
+        assert_eq!(
+            custom
+                .get_bytes(key)
+                .expect("'key' to be present")
+                .expect("stored value to be valid base64"),
+            value,
+            "get_bytes 'key'"
+        );
+        assert!(
+            custom.get_bytes("unexisting").is_none(),
+            "get_bytes 'unexisting' key"
+        );
+    }
+
+    #[test]
+    fn test_custom_get_bytes_rejects_invalid_base64() {
+        let key = "thumbnail";
+
+        let mut custom = Custom::default();
+        custom.insert(&format!("{}{}", "b64:", key), "not-a-multiple-of-4");
+        assert!(
+            custom.get_bytes(key).expect("'key' to be present").is_err(),
+            "get_bytes should reject a value whose length isn't a multiple of 4"
+        );
+
+        let mut custom = Custom::default();
+        custom.insert(&format!("{}{}", "b64:", key), "!!!!");
+        assert!(
+            custom.get_bytes(key).expect("'key' to be present").is_err(),
+            "get_bytes should reject a value with invalid base64 symbols"
+        );
+    }
+
+    #[test]
+    fn test_custom_fingerprint_is_order_independent() {
+        let a = Custom::with_entries(&[("key-a", "val-a"), ("key-b", "val-b")]);
+        let b = Custom::with_entries(&[("key-b", "val-b"), ("key-a", "val-a")]);
+
+        assert_eq!(
+            a.fingerprint(),
+            b.fingerprint(),
+            "fingerprint shouldn't depend on insertion order"
+        );
+    }
+
+    #[test]
+    fn test_custom_fingerprint_changes_with_content() {
+        let a = Custom::with_entries(&[("key-a", "val-a")]);
+        let b = Custom::with_entries(&[("key-a", "val-a-2")]);
+        let c = Custom::with_entries(&[("key-ab", "val-a")]);
+
+        assert_ne!(
+            a.fingerprint(),
+            b.fingerprint(),
+            "fingerprint should change when a value changes"
+        );
+        assert_ne!(
+            a.fingerprint(),
+            c.fingerprint(),
+            "fingerprint should change when a key changes"
+        );
+
+        let mut d = Custom::default();
+        assert_eq!(
+            d.fingerprint(),
+            Custom::default().fingerprint(),
+            "fingerprint of two empty instances should match"
+        );
+        d.insert("key-a", "val-a");
+        assert_eq!(
+            d.fingerprint(),
+            a.fingerprint(),
+            "fingerprint should match an equivalent instance built differently"
+        );
+    }
+
+    #[test]
+    fn test_custom_filter() {
+        use super::query::{and, key_has_prefix, not, value_eq};
+
+        let custom = Custom::with_entries(&[
+            ("image-board:title", "cat"),
+            ("image-board:author", "anon"),
+            ("other:title", "cat"),
+        ]);
+
+        let filtered = custom.filter(key_has_prefix("image-board:"));
+        assert_eq!(filtered.count(), 2, "count after filtering by key prefix");
+        assert_eq!(
+            filtered.get("image-board:title"),
+            Some("cat"),
+            "get 'image-board:title' from the filtered result"
+        );
+        assert_eq!(
+            filtered.get("image-board:author"),
+            Some("anon"),
+            "get 'image-board:author' from the filtered result"
+        );
+        assert_eq!(
+            filtered.get("other:title"),
+            None,
+            "get 'other:title' from the filtered result"
+        );
+
+        let filtered = custom.filter(and(key_has_prefix("image-board:"), value_eq("cat")));
+        assert_eq!(
+            filtered.count(),
+            1,
+            "count after filtering by key and value"
+        );
+        assert_eq!(
+            filtered.get("image-board:title"),
+            Some("cat"),
+            "get 'image-board:title' from the filtered result"
+        );
+
+        let filtered = custom.filter(not(key_has_prefix("image-board:")));
+        assert_eq!(filtered.count(), 1, "count after negating a predicate");
+        assert_eq!(
+            filtered.get("other:title"),
+            Some("cat"),
+            "get 'other:title' from the filtered result"
+        );
+    }
+
+    #[test]
+    fn test_custom_entry() {
+        let mut custom = Custom::default();
+
+        assert_eq!(
+            custom.entry("key-a").or_insert("val-a"),
+            "val-a",
+            "or_insert on a vacant entry"
+        );
+        assert_eq!(custom.get("key-a"), Some("val-a"));
+
+        assert_eq!(
+            custom.entry("key-a").or_insert("val-a-2"),
+            "val-a",
+            "or_insert on an occupied entry keeps the existing value"
+        );
+
+        custom
+            .entry("key-a")
+            .and_modify(|v| format!("{v}-modified"));
+        assert_eq!(custom.get("key-a"), Some("val-a-modified"));
+
+        custom.entry("key-b").and_modify(|v| v.to_uppercase());
+        assert_eq!(
+            custom.get("key-b"),
+            None,
+            "and_modify on a vacant entry does nothing"
+        );
+    }
+
+    #[test]
+    fn test_custom_extend_entries() {
+        let mut custom = Custom::with_entries(&[("key-a", "val-a")]);
+        custom.extend_entries(&[("key-a", "val-a-2"), ("key-b", "val-b")]);
+
+        assert_eq!(custom.count(), 2, "count after extend_entries");
+        assert_eq!(custom.get("key-a"), Some("val-a-2"));
+        assert_eq!(custom.get("key-b"), Some("val-b"));
+    }
+
+    #[test]
+    fn test_custom_retain() {
+        let mut custom =
+            Custom::with_entries(&[("image-board:title", "cat"), ("other:title", "cat")]);
+
+        custom.retain(|k, _v| k.starts_with("image-board:"));
+
+        assert_eq!(custom.count(), 1, "count after retain");
+        assert_eq!(custom.get("image-board:title"), Some("cat"));
+        assert_eq!(custom.get("other:title"), None);
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn test_custom_encrypt_decrypt_value() {
+        let key = "secret";
+        let value = "super secret value";
+        let key_material = [7u8; 32];
+
+        let mut custom = Custom::with_entries(&[(key, value)]);
+        custom
+            .encrypt_value(key, &key_material)
+            .expect("to encrypt 'key'");
+        assert_eq!(custom.get(key), None, "plaintext entry shouldn't remain");
+
+        custom
+            .decrypt_value(key, &key_material)
+            .expect("to decrypt 'key'");
+        assert_eq!(custom.get(key), Some(value), "get 'key' after decrypting");
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn test_custom_decrypt_value_rejects_wrong_key_material() {
+        let key = "secret";
+        let value = "super secret value";
+
+        let mut custom = Custom::with_entries(&[(key, value)]);
+        custom
+            .encrypt_value(key, &[1u8; 32])
+            .expect("to encrypt 'key'");
+
+        assert!(
+            custom.decrypt_value(key, &[2u8; 32]).is_err(),
+            "decrypting with the wrong key material should fail authentication"
+        );
+        assert!(
+            custom.get("enc:secret").is_some(),
+            "the encrypted entry must survive a failed decryption, not be lost"
+        );
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn test_custom_decrypt_value_rejects_value_swapped_from_another_key() {
+        let key_material = [9u8; 32];
+
+        let mut custom = Custom::with_entries(&[("field-a", "value-a"), ("field-b", "value-b")]);
+        custom
+            .encrypt_value("field-a", &key_material)
+            .expect("to encrypt 'field-a'");
+        custom
+            .encrypt_value("field-b", &key_material)
+            .expect("to encrypt 'field-b'");
+
+        // Paste field-a's ciphertext under field-b's encrypted entry.
+        let swapped = custom.get("enc:field-a").unwrap().to_owned();
+        custom.insert("enc:field-b", &swapped);
+
+        assert!(
+            custom.decrypt_value("field-b", &key_material).is_err(),
+            "a ciphertext pasted from another key shouldn't authenticate"
+        );
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn test_custom_encrypt_value_unexisting_key() {
+        let mut custom = Custom::default();
+        assert!(
+            custom.encrypt_value("unexisting", &[3u8; 32]).is_err(),
+            "encrypt_value on an unexisting key"
+        );
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn test_custom_decrypt_value_unexisting_key() {
+        let mut custom = Custom::with_entries(&[("key-a", "val-a")]);
+        assert!(
+            custom.decrypt_value("key-a", &[3u8; 32]).is_err(),
+            "decrypt_value on a key that was never encrypted"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_custom_to_json_from_json() {
+        let key1 = "key-a";
+        let val1 = "val-a";
+        let key2 = "key-b";
+        let val2 = "val-b";
+
+        let custom = Custom::with_entries(&[(key1, val1), (key2, val2)]);
+        let json = custom.to_json().expect("to serialize to JSON");
+
+        let from_json = Custom::from_json(&json).expect("to deserialize from JSON");
+        assert_eq!(from_json.count(), 2, "count");
+        assert_eq!(from_json.get(key1), Some(val1), "get: 'key1'");
+        assert_eq!(from_json.get(key2), Some(val2), "get: 'key2'");
+    }
+
     use crate::helpers::test::{assert_c_string, compare_c_string};
 
     #[test]
@@ -460,4 +1195,4 @@ mod test {
             assert_c_string(entry.value, val2);
         }
     }
-}
\ No newline at end of file
+}